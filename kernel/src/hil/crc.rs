@@ -0,0 +1,101 @@
+//! Traits and types for CRC computation.
+//!
+//! A `CRC` unit computes a cyclic redundancy check over a slice of bytes and
+//! reports the result asynchronously through a `Client`.  In addition to a
+//! handful of fixed algorithms named by `CrcAlg`, a unit may accept a full
+//! "Rocksoft"-model parameter block (`CrcParams`) so that userspace can
+//! request arbitrary checksums the fixed modes don't cover.
+
+use returncode::ReturnCode;
+
+/// A CRC algorithm understood directly by a hardware CRC unit.
+///
+/// See the `capsules::crc` module documentation for the polynomial and
+/// post-processing associated with each variant.
+#[derive(Copy, Clone, PartialEq)]
+pub enum CrcAlg {
+    Crc32,
+    Crc32C,
+    Sam4L16,
+    Sam4L32,
+    Sam4L32C,
+}
+
+/// A complete "Rocksoft"-model CRC definition.
+///
+/// Where `CrcAlg` names a closed set of algorithms, a `CrcParams` describes an
+/// arbitrary CRC by its parameters, letting callers compute checksums the
+/// hardware modes don't cover (CRC-8-Bluetooth, CRC-16-IBM-SDLC/X25,
+/// CRC-64-ECMA, and so on) without the kernel knowing each variant ahead of
+/// time.
+#[derive(Copy, Clone, PartialEq)]
+pub struct CrcParams {
+    /// Register width in bits; one of 8, 16, 32 or 64.
+    pub width: u8,
+
+    /// Generator polynomial in the low `width` bits, most-significant term
+    /// (which always equals one) omitted.
+    pub poly: u64,
+
+    /// Initial value loaded into the register before any input is consumed.
+    pub init: u64,
+
+    /// Whether each input byte is consumed least-significant-bit first.
+    pub reflect_in: bool,
+
+    /// Whether the register is bit-reversed before the final XOR.
+    pub reflect_out: bool,
+
+    /// Value XOR'd into the register to produce the final result.
+    pub final_xor: u64,
+}
+
+/// A hardware (or software-backed) CRC computation unit.
+pub trait CRC {
+    /// Return an implementation-defined version value for the unit.
+    fn get_version(&self) -> u32;
+
+    /// Begin a CRC computation over `data` using the fixed algorithm `alg`.
+    ///
+    /// The result, with any algorithm-defined post-processing already applied,
+    /// is delivered through `Client::receive_result`.  Returns `ESIZE` if
+    /// `data` is longer than `max_input_len`.
+    fn compute(&self, data: &[u8], alg: CrcAlg) -> ReturnCode;
+
+    /// Begin one segment of a chunked CRC computation over `data`.
+    ///
+    /// `seed` is the *raw* register remainder carried over from the previous
+    /// segment, or `None` to start from the algorithm's initial value.  When
+    /// `finalize` is `false` the value delivered through
+    /// `Client::receive_result` is the raw register remainder — without the
+    /// bit-reversal and inversion some algorithms apply — so it can seed the
+    /// next segment unchanged.  When `finalize` is `true` the usual
+    /// post-processing is applied, yielding the final result.  This lets a
+    /// caller split a buffer larger than `max_input_len` into hardware-sized
+    /// pieces and chain them.
+    fn compute_segment(&self, data: &[u8], alg: CrcAlg, seed: Option<u32>, finalize: bool)
+                       -> ReturnCode;
+
+    /// Begin a CRC computation over `data` using a full Rocksoft-model
+    /// parameter block.  Units that implement only the fixed `CrcAlg` modes
+    /// may return `ENOSUPPORT`.
+    fn compute_with_params(&self, data: &[u8], params: CrcParams) -> ReturnCode;
+
+    /// The maximum number of bytes the unit accepts in a single pass, or `0`
+    /// if it imposes no limit.
+    fn max_input_len(&self) -> usize;
+
+    /// A bitmask of the `CrcAlg` modes this unit implements natively in
+    /// hardware.  Bit *n* corresponds to the algorithm numbered *n* in the
+    /// userspace `command` interface.
+    fn supported_algorithms(&self) -> u32;
+
+    /// Disable the unit, releasing any power or clocks it holds.
+    fn disable(&self);
+}
+
+/// Receiver for the result of a `CRC` computation.
+pub trait Client {
+    /// Called when a CRC computation completes, carrying its result.
+    fn receive_result(&self, result: u32);
+}